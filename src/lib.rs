@@ -1,7 +1,8 @@
+pub mod rustc;
 mod serialization;
 mod stringtable;
 
 pub use crate::serialization::{Addr, SerializationSink};
 pub use crate::stringtable::{
-    SerializableString, StringId, StringRef, StringTable, StringTableBuilder,
+    SerializableString, StringComponent, StringId, StringRef, StringTable, StringTableBuilder,
 };
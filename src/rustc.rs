@@ -0,0 +1,124 @@
+//! Event kinds with special meaning for rustc's self-profiler.
+//!
+//! The kinds defined here have fixed, pre-reserved `StringId`s, so that
+//! tools can recognize them without comparing strings. They are taken from
+//! the top of the pre-reserved range, leaving the low ids free for the
+//! embedder (e.g. for query names).
+
+use crate::serialization::SerializationSink;
+use crate::stringtable::{StringId, StringTableBuilder, MAX_PRE_RESERVED_STRING_ID};
+
+/// The time a thread spent waiting for a query that is being executed by
+/// another thread. This time should not be counted as self-time of the
+/// enclosing query.
+pub const QUERY_BLOCKED_EVENT_KIND: &str = "QueryBlocked";
+pub const QUERY_BLOCKED_EVENT_KIND_ID: StringId = reserved_kind_id(0);
+
+/// A query result that was found in the in-memory cache, so the query
+/// provider was not invoked.
+pub const QUERY_CACHE_HIT_EVENT_KIND: &str = "QueryCacheHit";
+pub const QUERY_CACHE_HIT_EVENT_KIND_ID: StringId = reserved_kind_id(1);
+
+/// The execution of a query provider.
+pub const QUERY_EVENT_KIND: &str = "Query";
+pub const QUERY_EVENT_KIND_ID: StringId = reserved_kind_id(2);
+
+/// Any activity that is not a query, e.g. a compiler pass.
+pub const GENERIC_ACTIVITY_EVENT_KIND: &str = "GenericActivity";
+pub const GENERIC_ACTIVITY_EVENT_KIND_ID: StringId = reserved_kind_id(3);
+
+/// Loading a query result from the incremental compilation cache.
+pub const INCREMENTAL_LOAD_RESULT_EVENT_KIND: &str = "IncrementalLoadResult";
+pub const INCREMENTAL_LOAD_RESULT_EVENT_KIND_ID: StringId = reserved_kind_id(4);
+
+/// Hashing a query result for incremental compilation.
+pub const INCREMENTAL_RESULT_HASHING_EVENT_KIND: &str = "IncrementalResultHashing";
+pub const INCREMENTAL_RESULT_HASHING_EVENT_KIND_ID: StringId = reserved_kind_id(5);
+
+/// Work done by the code generation backend, e.g. optimizing a codegen unit.
+pub const CODEGEN_EVENT_KIND: &str = "Codegen";
+pub const CODEGEN_EVENT_KIND_ID: StringId = reserved_kind_id(6);
+
+/// The size of an artifact produced by the compiler, e.g. the metadata blob.
+pub const ARTIFACT_SIZE_EVENT_KIND: &str = "ArtifactSize";
+pub const ARTIFACT_SIZE_EVENT_KIND_ID: StringId = reserved_kind_id(7);
+
+/// All event kinds defined in this module, together with their ids.
+pub const RESERVED_EVENT_KINDS: &[(StringId, &str)] = &[
+    (QUERY_BLOCKED_EVENT_KIND_ID, QUERY_BLOCKED_EVENT_KIND),
+    (QUERY_CACHE_HIT_EVENT_KIND_ID, QUERY_CACHE_HIT_EVENT_KIND),
+    (QUERY_EVENT_KIND_ID, QUERY_EVENT_KIND),
+    (GENERIC_ACTIVITY_EVENT_KIND_ID, GENERIC_ACTIVITY_EVENT_KIND),
+    (
+        INCREMENTAL_LOAD_RESULT_EVENT_KIND_ID,
+        INCREMENTAL_LOAD_RESULT_EVENT_KIND,
+    ),
+    (
+        INCREMENTAL_RESULT_HASHING_EVENT_KIND_ID,
+        INCREMENTAL_RESULT_HASHING_EVENT_KIND,
+    ),
+    (CODEGEN_EVENT_KIND_ID, CODEGEN_EVENT_KIND),
+    (ARTIFACT_SIZE_EVENT_KIND_ID, ARTIFACT_SIZE_EVENT_KIND),
+];
+
+const fn reserved_kind_id(index: u32) -> StringId {
+    StringId::reserved(MAX_PRE_RESERVED_STRING_ID - index)
+}
+
+/// Writes the strings for all event kinds defined in this module to the
+/// string table. This has to be done exactly once per string table.
+pub fn alloc_reserved_event_kinds<S: SerializationSink>(builder: &StringTableBuilder<S>) {
+    for &(id, kind) in RESERVED_EVENT_KINDS {
+        builder.alloc_with_reserved_id(id, kind);
+    }
+}
+
+/// Returns the reserved id for the given event kind, if it is one of the
+/// kinds defined in this module.
+pub fn reserved_event_kind_id(kind: &str) -> Option<StringId> {
+    RESERVED_EVENT_KINDS
+        .iter()
+        .find(|&&(_, name)| name == kind)
+        .map(|&(id, _)| id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serialization::test::TestSink;
+    use crate::stringtable::StringTable;
+    use std::sync::Arc;
+
+    #[test]
+    fn reserved_ids_are_distinct() {
+        for (i, &(id, _)) in RESERVED_EVENT_KINDS.iter().enumerate() {
+            for &(other_id, _) in &RESERVED_EVENT_KINDS[i + 1..] {
+                assert_ne!(id, other_id);
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_event_kinds() {
+        let data_sink = Arc::new(TestSink::new());
+        let index_sink = Arc::new(TestSink::new());
+
+        let regular_id = {
+            let builder = StringTableBuilder::new(data_sink.clone(), index_sink.clone());
+            alloc_reserved_event_kinds(&builder);
+            builder.alloc("regular")
+        };
+
+        let data_bytes = Arc::try_unwrap(data_sink).unwrap().into_bytes();
+        let index_bytes = Arc::try_unwrap(index_sink).unwrap().into_bytes();
+
+        let string_table = StringTable::new(&data_bytes, &index_bytes);
+
+        for &(id, kind) in RESERVED_EVENT_KINDS {
+            assert_eq!(string_table.get(id).to_string(), kind);
+            assert_eq!(reserved_event_kind_id(kind), Some(id));
+        }
+        assert_eq!(string_table.get(regular_id).to_string(), "regular");
+        assert_eq!(reserved_event_kind_id("regular"), None);
+    }
+}
@@ -11,6 +11,28 @@ pub trait SerializationSink {
     fn write_atomic<W>(&self, num_bytes: usize, write: W) -> Addr
     where
         W: FnOnce(&mut [u8]);
+
+    /// Writes the given bytes as a single, contiguous record and returns its
+    /// address. Sinks that can append without zeroing the target memory
+    /// first should override this.
+    fn write_bytes_atomic(&self, bytes: &[u8]) -> Addr {
+        self.write_atomic(bytes.len(), |mem| mem.copy_from_slice(bytes))
+    }
+
+    /// Writes the concatenation of `bufs` as a single, contiguous record and
+    /// returns its address. This avoids having to copy the pieces into an
+    /// intermediate buffer first.
+    fn write_bytes_vectored_atomic(&self, bufs: &[&[u8]]) -> Addr {
+        let num_bytes = bufs.iter().map(|buf| buf.len()).sum();
+
+        self.write_atomic(num_bytes, |mut mem| {
+            for buf in bufs {
+                let (head, tail) = mem.split_at_mut(buf.len());
+                head.copy_from_slice(buf);
+                mem = tail;
+            }
+        })
+    }
 }
 
 #[cfg(test)]
@@ -49,6 +71,22 @@ pub mod test {
 
             Addr(start as u32)
         }
+
+        fn write_bytes_atomic(&self, bytes: &[u8]) -> Addr {
+            self.write_bytes_vectored_atomic(&[bytes])
+        }
+
+        fn write_bytes_vectored_atomic(&self, bufs: &[&[u8]]) -> Addr {
+            let mut data = self.data.lock().unwrap();
+
+            let start = data.len();
+
+            for buf in bufs {
+                data.extend_from_slice(buf);
+            }
+
+            Addr(start as u32)
+        }
     }
 
     impl std::fmt::Debug for TestSink {
@@ -56,4 +94,39 @@ pub mod test {
             write!(f, "TestSink")
         }
     }
+
+    #[test]
+    fn vectored_writes() {
+        struct DefaultSink(TestSink);
+
+        impl SerializationSink for DefaultSink {
+            fn write_atomic<W>(&self, num_bytes: usize, write: W) -> Addr
+            where
+                W: FnOnce(&mut [u8]),
+            {
+                self.0.write_atomic(num_bytes, write)
+            }
+        }
+
+        fn write_all<S: SerializationSink>(sink: &S) {
+            assert_eq!(sink.write_bytes_atomic(b"abc"), Addr(0));
+            assert_eq!(sink.write_bytes_atomic(b""), Addr(3));
+            assert_eq!(
+                sink.write_bytes_vectored_atomic(&[b"de", b"", b"fgh"]),
+                Addr(3)
+            );
+            assert_eq!(sink.write_bytes_vectored_atomic(&[]), Addr(8));
+            assert_eq!(sink.write_bytes_atomic(b"ij"), Addr(8));
+        }
+
+        let test_sink = TestSink::new();
+        write_all(&test_sink);
+
+        let default_sink = DefaultSink(TestSink::new());
+        write_all(&default_sink);
+
+        let expected = b"abcdefghij".to_vec();
+        assert_eq!(test_sink.into_bytes(), expected);
+        assert_eq!(default_sink.0.into_bytes(), expected);
+    }
 }
@@ -17,12 +17,21 @@ use byteorder::{ByteOrder, LittleEndian};
 use rustc_hash::FxHashMap;
 use std::borrow::Cow;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 /// A `StringId` is used to identify a string in the `StringTable`.
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub struct StringId(u32);
 
+impl StringId {
+    /// Creates a `StringId` from the pre-reserved range, i.e. one that can be
+    /// passed to `StringTableBuilder::alloc_with_reserved_id`.
+    pub const fn reserved(id: u32) -> StringId {
+        assert!(id <= MAX_PRE_RESERVED_STRING_ID);
+        StringId(id)
+    }
+}
+
 // Tags for the binary encoding of strings
 
 /// Marks the end of a string component list.
@@ -34,13 +43,15 @@ const TAG_STR_VAL: u8 = 1;
 /// Marks a component that contains the ID of another string.
 const TAG_STR_REF: u8 = 2;
 
-const MAX_PRE_RESERVED_STRING_ID: u32 = std::u32::MAX / 2;
+pub(crate) const MAX_PRE_RESERVED_STRING_ID: u32 = u32::MAX / 2;
 
 /// Write-only version of the string table
 pub struct StringTableBuilder<S: SerializationSink> {
     data_sink: Arc<S>,
     index_sink: Arc<S>,
     id_counter: AtomicU32, // initialized to MAX_PRE_RESERVED_STRING_ID + 1
+    // Keyed by the address and length of a `&'static str`
+    static_strings: Mutex<FxHashMap<(usize, usize), StringId>>,
 }
 
 /// Anything that implements `SerializableString` can be written to a
@@ -64,7 +75,7 @@ impl SerializableString for str {
     }
 
     fn serialize(&self, bytes: &mut [u8]) {
-        assert!(self.len() <= std::u16::MAX as usize);
+        assert!(self.len() <= u16::MAX as usize);
         let last_byte_index = bytes.len() - 1;
         bytes[0] = TAG_STR_VAL;
         LittleEndian::write_u16(&mut bytes[1..3], self.len() as u16);
@@ -79,21 +90,52 @@ pub enum StringComponent<'s> {
     Ref(StringId),
 }
 
+// A composite string is encoded as a list of components
+//
+// [TAG_STR_VAL, len: u16, utf8_bytes] or [TAG_STR_REF, id: u32]
+//
+// followed by a TAG_TERMINATOR.
 impl<'a> SerializableString for [StringComponent<'a>] {
     fn serialized_size(&self) -> usize {
-        unimplemented!()
+        let components_size: usize = self
+            .iter()
+            .map(|component| match *component {
+                StringComponent::Value(s) => 1 + 2 + s.len(), // tag, len, actual bytes
+                StringComponent::Ref(_) => 1 + 4,             // tag, string id
+            })
+            .sum();
+
+        components_size + 1 // terminator
     }
 
-    fn serialize(&self, _bytes: &mut [u8]) {
-        unimplemented!()
+    fn serialize(&self, bytes: &mut [u8]) {
+        let mut pos = 0;
+
+        for component in self.iter() {
+            match *component {
+                StringComponent::Value(s) => {
+                    assert!(s.len() <= u16::MAX as usize);
+                    bytes[pos] = TAG_STR_VAL;
+                    LittleEndian::write_u16(&mut bytes[pos + 1..pos + 3], s.len() as u16);
+                    bytes[pos + 3..pos + 3 + s.len()].copy_from_slice(s.as_bytes());
+                    pos += 3 + s.len();
+                }
+                StringComponent::Ref(id) => {
+                    bytes[pos] = TAG_STR_REF;
+                    LittleEndian::write_u32(&mut bytes[pos + 1..pos + 5], id.0);
+                    pos += 5;
+                }
+            }
+        }
+
+        bytes[pos] = TAG_TERMINATOR;
+        debug_assert_eq!(pos + 1, bytes.len());
     }
 }
 
-fn serialize_index_entry<S: SerializationSink>(sink: &S, id: StringId, addr: Addr) {
-    sink.write_atomic(8, |bytes| {
-        LittleEndian::write_u32(&mut bytes[0..4], id.0);
-        LittleEndian::write_u32(&mut bytes[4..8], addr.0);
-    });
+fn serialize_index_entry(bytes: &mut [u8], id: StringId, addr: Addr) {
+    LittleEndian::write_u32(&mut bytes[0..4], id.0);
+    LittleEndian::write_u32(&mut bytes[4..8], addr.0);
 }
 
 fn deserialize_index_entry(bytes: &[u8]) -> (StringId, Addr) {
@@ -109,6 +151,7 @@ impl<S: SerializationSink> StringTableBuilder<S> {
             data_sink,
             index_sink,
             id_counter: AtomicU32::new(MAX_PRE_RESERVED_STRING_ID + 1),
+            static_strings: Mutex::new(FxHashMap::default()),
         }
     }
 
@@ -129,13 +172,71 @@ impl<S: SerializationSink> StringTableBuilder<S> {
         id
     }
 
+    /// Allocates a `&'static str`, caching the resulting id by the string's
+    /// address so that repeated calls with the same string return the same
+    /// `StringId` without hashing or re-serializing its contents.
+    pub fn alloc_static(&self, s: &'static str) -> StringId {
+        let key = (s.as_ptr() as usize, s.len());
+
+        let mut static_strings = self.static_strings.lock().unwrap();
+
+        *static_strings.entry(key).or_insert_with(|| self.alloc(s))
+    }
+
+    /// Allocates all strings yielded by `strings` under a contiguous range of
+    /// ids and returns the first one. The `n`-th string gets the id
+    /// `StringId(first.0 + n)`. String data and index entries are each
+    /// written with a single `write_atomic` call.
+    pub fn alloc_many<'s, I, STR>(&self, strings: I) -> StringId
+    where
+        I: IntoIterator<Item = &'s STR>,
+        STR: SerializableString + ?Sized + 's,
+    {
+        let strings: Vec<&STR> = strings.into_iter().collect();
+        let num_strings = strings.len() as u32;
+
+        let first_id = self.id_counter.fetch_add(num_strings, Ordering::SeqCst);
+        debug_assert!(first_id > MAX_PRE_RESERVED_STRING_ID);
+
+        if strings.is_empty() {
+            return StringId(first_id);
+        }
+
+        let mut ranges = Vec::with_capacity(strings.len());
+        let mut size_in_bytes = 0;
+
+        for s in &strings {
+            let start = size_in_bytes;
+            size_in_bytes += s.serialized_size();
+            ranges.push(start..size_in_bytes);
+        }
+
+        let addr = self.data_sink.write_atomic(size_in_bytes, |mem| {
+            for (s, range) in strings.iter().zip(&ranges) {
+                s.serialize(&mut mem[range.clone()]);
+            }
+        });
+
+        self.index_sink.write_atomic(strings.len() * 8, |mem| {
+            for (i, (entry, range)) in mem.chunks_mut(8).zip(&ranges).enumerate() {
+                let id = StringId(first_id + i as u32);
+                let entry_addr = Addr(addr.0 + range.start as u32);
+                serialize_index_entry(entry, id, entry_addr);
+            }
+        });
+
+        StringId(first_id)
+    }
+
     fn alloc_unchecked<STR: SerializableString + ?Sized>(&self, id: StringId, s: &STR) {
         let size_in_bytes = s.serialized_size();
         let addr = self.data_sink.write_atomic(size_in_bytes, |mem| {
             s.serialize(mem);
         });
 
-        serialize_index_entry(&*self.index_sink, id, addr);
+        self.index_sink.write_atomic(8, |bytes| {
+            serialize_index_entry(bytes, id, addr);
+        });
     }
 }
 
@@ -196,7 +297,9 @@ impl<'st> StringRef<'st> {
                 }
 
                 TAG_STR_REF => {
-                    unimplemented!();
+                    let id = LittleEndian::read_u32(&self.table.string_data[pos + 1..pos + 5]);
+                    self.table.get(StringId(id)).write_to_string(output);
+                    pos += 5;
                 }
 
                 TAG_TERMINATOR => return,
@@ -216,14 +319,14 @@ pub struct StringTable<'data> {
 
 impl<'data> StringTable<'data> {
     pub fn new(string_data: &'data [u8], index_data: &[u8]) -> StringTable<'data> {
-        assert!(index_data.len() % 8 == 0);
+        assert_eq!(index_data.len() % 8, 0);
 
         let index: FxHashMap<_, _> = index_data.chunks(8).map(deserialize_index_entry).collect();
 
         StringTable { string_data, index }
     }
 
-    pub fn get(&self, id: StringId) -> StringRef {
+    pub fn get(&self, id: StringId) -> StringRef<'_> {
         StringRef { id, table: self }
     }
 }
@@ -274,4 +377,130 @@ mod test {
             assert_eq!(str_ref.to_string(), write_to);
         }
     }
+
+    #[test]
+    fn composite_strings() {
+        use crate::serialization::test::TestSink;
+
+        let data_sink = Arc::new(TestSink::new());
+        let index_sink = Arc::new(TestSink::new());
+
+        let (prefix_id, empty_id, with_args_id, nested_id) = {
+            let builder = StringTableBuilder::new(data_sink.clone(), index_sink.clone());
+
+            let prefix_id = builder.alloc("typeck_tables_of");
+            let empty_id = builder.alloc(&[][..]);
+            let with_args_id = builder.alloc(
+                &[
+                    StringComponent::Ref(prefix_id),
+                    StringComponent::Value("("),
+                    StringComponent::Ref(empty_id),
+                    StringComponent::Value("main)"),
+                ][..],
+            );
+            let nested_id = builder.alloc(
+                &[
+                    StringComponent::Value(">"),
+                    StringComponent::Ref(with_args_id),
+                    StringComponent::Ref(prefix_id),
+                ][..],
+            );
+
+            (prefix_id, empty_id, with_args_id, nested_id)
+        };
+
+        let data_bytes = Arc::try_unwrap(data_sink).unwrap().into_bytes();
+        let index_bytes = Arc::try_unwrap(index_sink).unwrap().into_bytes();
+
+        let string_table = StringTable::new(&data_bytes, &index_bytes);
+
+        let expected = &[
+            (prefix_id, "typeck_tables_of"),
+            (empty_id, ""),
+            (with_args_id, "typeck_tables_of(main)"),
+            (nested_id, ">typeck_tables_of(main)typeck_tables_of"),
+        ];
+
+        for &(id, expected_string) in expected {
+            let str_ref = string_table.get(id);
+
+            assert_eq!(str_ref.to_string(), expected_string);
+
+            let mut write_to = String::new();
+            str_ref.write_to_string(&mut write_to);
+            assert_eq!(write_to, expected_string);
+        }
+    }
+
+    #[test]
+    fn alloc_static() {
+        use crate::serialization::test::TestSink;
+
+        static LABEL: &str = "some_static_label";
+
+        let data_sink = Arc::new(TestSink::new());
+        let index_sink = Arc::new(TestSink::new());
+
+        let (label_id, other_id) = {
+            let builder = StringTableBuilder::new(data_sink.clone(), index_sink.clone());
+
+            let label_id = builder.alloc_static(LABEL);
+            assert_eq!(builder.alloc_static(LABEL), label_id);
+
+            let other_id = builder.alloc_static(&LABEL[5..]);
+            assert_ne!(other_id, label_id);
+            assert_eq!(builder.alloc_static(LABEL), label_id);
+
+            (label_id, other_id)
+        };
+
+        let data_bytes = Arc::try_unwrap(data_sink).unwrap().into_bytes();
+        let index_bytes = Arc::try_unwrap(index_sink).unwrap().into_bytes();
+
+        // Only one index entry per distinct static string
+        assert_eq!(index_bytes.len(), 2 * 8);
+
+        let string_table = StringTable::new(&data_bytes, &index_bytes);
+
+        assert_eq!(string_table.get(label_id).to_string(), LABEL);
+        assert_eq!(string_table.get(other_id).to_string(), "static_label");
+    }
+
+    #[test]
+    fn alloc_many() {
+        use crate::serialization::test::TestSink;
+
+        let data_sink = Arc::new(TestSink::new());
+        let index_sink = Arc::new(TestSink::new());
+
+        let batch_strings: &[&str] = &["abc", "", "xyz", "g2h9284hgjv282y32983849&35g5y"];
+
+        let (single_id, first_id, empty_id, last_id) = {
+            let builder = StringTableBuilder::new(data_sink.clone(), index_sink.clone());
+
+            let single_id = builder.alloc("before");
+            let first_id = builder.alloc_many(batch_strings.iter().cloned());
+            let empty_id = builder.alloc_many(std::iter::empty::<&str>());
+            let last_id = builder.alloc("after");
+
+            (single_id, first_id, empty_id, last_id)
+        };
+
+        assert_eq!(first_id.0, single_id.0 + 1);
+        assert_eq!(empty_id.0, first_id.0 + batch_strings.len() as u32);
+        assert_eq!(last_id, empty_id);
+
+        let data_bytes = Arc::try_unwrap(data_sink).unwrap().into_bytes();
+        let index_bytes = Arc::try_unwrap(index_sink).unwrap().into_bytes();
+
+        let string_table = StringTable::new(&data_bytes, &index_bytes);
+
+        assert_eq!(string_table.get(single_id).to_string(), "before");
+        assert_eq!(string_table.get(last_id).to_string(), "after");
+
+        for (i, &expected_string) in batch_strings.iter().enumerate() {
+            let id = StringId(first_id.0 + i as u32);
+            assert_eq!(string_table.get(id).to_string(), expected_string);
+        }
+    }
 }